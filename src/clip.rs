@@ -0,0 +1,319 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// The payload of a captured clip. Most clips are plain text, but the
+/// system clipboard can also carry a bitmap image or an HTML fragment
+/// (with its plain-text rendering alongside, the way browsers publish
+/// rich-text copies).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClipContent {
+    Text(String),
+    Image {
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    },
+    Html {
+        html: String,
+        plain: String,
+    },
+}
+
+impl ClipContent {
+    /// A short, single-variant key used to dedup entries in
+    /// `ClipboardStack::push` without formatting image bytes as a
+    /// string: images compare by a hash of their pixels, text/HTML by
+    /// their textual content. Also doubles as a cache key for loaded
+    /// image textures in the UI layer.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            ClipContent::Text(text) => {
+                0u8.hash(&mut hasher);
+                text.hash(&mut hasher);
+            }
+            ClipContent::Image {
+                width,
+                height,
+                rgba,
+            } => {
+                1u8.hash(&mut hasher);
+                width.hash(&mut hasher);
+                height.hash(&mut hasher);
+                rgba.hash(&mut hasher);
+            }
+            ClipContent::Html { html, .. } => {
+                2u8.hash(&mut hasher);
+                html.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ClipContent::Text(text) => text.is_empty(),
+            ClipContent::Image { width, height, .. } => *width == 0 || *height == 0,
+            ClipContent::Html { html, plain } => html.is_empty() && plain.is_empty(),
+        }
+    }
+}
+
+/// Which X11 selection a clip came from. `CLIPBOARD` is the familiar
+/// Ctrl+C/Ctrl+V selection; `PRIMARY` holds whatever text is currently
+/// highlighted and is pasted with a middle-click. Platforms without a
+/// PRIMARY selection only ever produce `Clipboard` entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ClipboardKind {
+    #[default]
+    Clipboard,
+    Primary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClipEntry {
+    pub content: ClipContent,
+    #[serde(with = "unix_time")]
+    pub timestamp: SystemTime,
+    /// When set, this entry is kept in memory for the session but is
+    /// skipped by `persist::save` — an escape hatch for clips the user
+    /// doesn't want written to disk (passwords, tokens, etc.).
+    #[serde(default)]
+    pub no_persist: bool,
+    #[serde(default)]
+    pub kind: ClipboardKind,
+    /// `content.content_hash()`, computed once at capture instead of
+    /// every frame: for image clips that hash scans the whole pixel
+    /// buffer, which is too expensive to redo in the UI's per-row loop
+    /// or on every `ClipboardStack::push`. Not persisted — recomputed
+    /// on load, since it's a pure function of `content`.
+    #[serde(skip)]
+    pub content_hash: u64,
+}
+
+impl ClipEntry {
+    pub fn new(content: ClipContent) -> Self {
+        let content_hash = content.content_hash();
+        Self {
+            content,
+            timestamp: SystemTime::now(),
+            no_persist: false,
+            kind: ClipboardKind::Clipboard,
+            content_hash,
+        }
+    }
+
+    pub fn with_kind(content: ClipContent, kind: ClipboardKind) -> Self {
+        let content_hash = content.content_hash();
+        Self {
+            content,
+            timestamp: SystemTime::now(),
+            no_persist: false,
+            kind,
+            content_hash,
+        }
+    }
+}
+
+/// (De)serializes `SystemTime` as a unix epoch in whole seconds, since
+/// `SystemTime` itself has no stable serde representation.
+mod unix_time {
+    use super::SystemTime;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        secs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let secs = u64::deserialize(deserializer)?;
+        Ok(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+}
+
+pub struct ClipboardStack {
+    entries: VecDeque<ClipEntry>,
+    max_size: usize,
+    /// Single-character pin slots (`a`-`z`), a register-based model
+    /// similar to vi/tmux: a clip assigned here survives both the
+    /// rolling `max_size` eviction in `push` and `clear()`, giving
+    /// durable storage for frequently reused snippets.
+    registers: HashMap<char, ClipEntry>,
+}
+
+impl ClipboardStack {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_size,
+            registers: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, entry: ClipEntry) {
+        let key = entry.content_hash;
+        self.entries.retain(|e| e.content_hash != key);
+
+        self.entries.push_front(entry);
+
+        if self.entries.len() > self.max_size {
+            self.entries.pop_back();
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ClipEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ClipEntry> {
+        self.entries.get_mut(index)
+    }
+
+    /// Appends an entry straight onto the back without the dedup/
+    /// eviction dance in `push`, for restoring history on load in the
+    /// order it was saved (newest first, as `iter` yields it).
+    pub fn push_back_raw(&mut self, entry: ClipEntry) {
+        self.entries.push_back(entry);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ClipEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Assigns a clip to a named register (`a`-`z`), where it's kept
+    /// indefinitely regardless of `max_size` eviction or `clear()`.
+    /// Overwrites whatever was previously pinned to that label.
+    pub fn pin(&mut self, label: char, entry: ClipEntry) {
+        self.registers.insert(label, entry);
+    }
+
+    pub fn unpin(&mut self, label: char) {
+        self.registers.remove(&label);
+    }
+
+    /// Pinned registers in label order, for a stable display in the UI.
+    pub fn registers(&self) -> Vec<(char, &ClipEntry)> {
+        let mut regs: Vec<(char, &ClipEntry)> =
+            self.registers.iter().map(|(&c, e)| (c, e)).collect();
+        regs.sort_by_key(|(label, _)| *label);
+        regs
+    }
+
+    pub fn register(&self, label: char) -> Option<&ClipEntry> {
+        self.registers.get(&label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_matches_for_identical_text() {
+        let a = ClipContent::Text("hello".to_string());
+        let b = ClipContent::Text("hello".to_string());
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn content_hash_differs_across_variants_with_the_same_text() {
+        let text = ClipContent::Text("hello".to_string());
+        let html = ClipContent::Html {
+            html: "<b>unused</b>".to_string(),
+            plain: "hello".to_string(),
+        };
+        // `Html`'s hash is keyed on `html`, not `plain`, so this also
+        // confirms the two variants don't collide just because their
+        // plain-text rendering happens to match.
+        assert_ne!(text.content_hash(), html.content_hash());
+    }
+
+    #[test]
+    fn content_hash_differs_for_images_with_different_pixels() {
+        let a = ClipContent::Image {
+            width: 1,
+            height: 1,
+            rgba: vec![0, 0, 0, 255],
+        };
+        let b = ClipContent::Image {
+            width: 1,
+            height: 1,
+            rgba: vec![255, 255, 255, 255],
+        };
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn push_evicts_oldest_once_past_max_size() {
+        let mut stack = ClipboardStack::new(2);
+        stack.push(ClipEntry::new(ClipContent::Text("one".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("two".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("three".to_string())));
+
+        assert_eq!(stack.len(), 2);
+        let remaining: Vec<&str> = stack
+            .iter()
+            .map(|e| match &e.content {
+                ClipContent::Text(t) => t.as_str(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(remaining, vec!["three", "two"]);
+    }
+
+    #[test]
+    fn push_dedups_equal_content_instead_of_keeping_both() {
+        let mut stack = ClipboardStack::new(10);
+        stack.push(ClipEntry::new(ClipContent::Text("dup".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("other".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("dup".to_string())));
+
+        assert_eq!(stack.len(), 2);
+        // The re-pushed duplicate moves back to the front instead of
+        // leaving a stale copy further back in the deque.
+        assert_eq!(stack.get(0).unwrap().content, ClipContent::Text("dup".to_string()));
+    }
+
+    #[test]
+    fn registers_survive_clear() {
+        let mut stack = ClipboardStack::new(10);
+        stack.push(ClipEntry::new(ClipContent::Text("history".to_string())));
+        stack.pin('a', ClipEntry::new(ClipContent::Text("pinned".to_string())));
+
+        stack.clear();
+
+        assert_eq!(stack.len(), 0);
+        assert_eq!(
+            stack.register('a').unwrap().content,
+            ClipContent::Text("pinned".to_string())
+        );
+    }
+
+    #[test]
+    fn registers_survive_max_size_eviction() {
+        let mut stack = ClipboardStack::new(1);
+        stack.pin('a', ClipEntry::new(ClipContent::Text("pinned".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("one".to_string())));
+        stack.push(ClipEntry::new(ClipContent::Text("two".to_string())));
+
+        assert_eq!(stack.len(), 1);
+        assert!(stack.register('a').is_some());
+    }
+}
@@ -1,198 +1,325 @@
-use std::collections::VecDeque;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
 
 use eframe::egui;
-use clipboard::{ClipboardProvider, ClipboardContext};
 
+mod clip;
+mod monitor;
+mod persist;
+mod provider;
+use clip::{ClipContent, ClipEntry, ClipboardKind, ClipboardStack};
+use provider::{get_clipboard_provider, ClipboardProvider};
 
-#[derive(Clone, Debug)]
-struct ClipEntry {
-    content: String,
-    timestamp: std::time::SystemTime,
-}
-
-impl ClipEntry {
-    fn new(content: String) -> Self {
-        Self {
-            content,
-            timestamp: std::time::SystemTime::now(),
-        }
-    }
-}
-
-
-struct ClipboardStack {
-    entries: VecDeque<ClipEntry>,
-    max_size: usize,
-}
-
-impl ClipboardStack {
-    fn new(max_size: usize) -> Self {
-        Self {
-            entries: VecDeque::new(),
-            max_size,
-        }
-    }
-
-    fn push(&mut self, entry: ClipEntry) {
-
-        self.entries.retain(|e| e.content != entry.content);
-        
-        
-        self.entries.push_front(entry);
-        
-        
-        if self.entries.len() > self.max_size {
-            self.entries.pop_back();
-        }
-    }
-
-    fn get(&self, index: usize) -> Option<&ClipEntry> {
-        self.entries.get(index)
-    }
-
-    fn len(&self) -> usize {
-        self.entries.len()
-    }
-
-    fn iter(&self) -> impl Iterator<Item = &ClipEntry> {
-        self.entries.iter()
-    }
-
-    fn clear(&mut self) {
-        self.entries.clear();
+/// Truncates `s` to at most `max_chars` characters, appending `...` if
+/// it was cut short. Walks char boundaries rather than slicing by byte
+/// index, since a fixed byte offset can land in the middle of a
+/// multibyte character and panic.
+fn truncate_preview(s: &str, max_chars: usize) -> String {
+    if s.chars().count() > max_chars {
+        format!("{}...", s.chars().take(max_chars).collect::<String>())
+    } else {
+        s.to_string()
     }
 }
 
-
 struct ClipboardApp {
     clipboard_stack: ClipboardStack,
-    clipboard_ctx: ClipboardContext,
-    last_clipboard_content: String,
-    monitor_interval: Duration,
+    clipboard_provider: Box<dyn ClipboardProvider>,
+    clip_events: Receiver<(ClipContent, ClipboardKind)>,
+    /// Last-seen content per selection, so a PRIMARY change doesn't get
+    /// compared against the CLIPBOARD's last value (and vice versa).
+    last_clipboard: ClipContent,
+    last_primary: ClipContent,
     auto_monitor: bool,
     search_filter: String,
+    /// When set, `filtered_entries` only shows clips from this
+    /// selection; `None` shows both CLIPBOARD and PRIMARY entries.
+    kind_filter: Option<ClipboardKind>,
+    /// Loaded image thumbnails, keyed by `ClipContent::content_hash` so
+    /// they survive the entry shifting position in the stack and don't
+    /// get re-uploaded to the GPU every frame.
+    image_textures: HashMap<u64, egui::TextureHandle>,
 }
 
 impl ClipboardApp {
-    fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let clipboard_stack = ClipboardStack::new(50);
-        let mut clipboard_ctx: ClipboardContext = ClipboardProvider::new()?;
-        
-        
-        let initial_content = clipboard_ctx.get_contents().unwrap_or_default();
-        
+    fn new(ctx: egui::Context) -> Result<Self, Box<dyn std::error::Error>> {
+        let clipboard_stack = persist::load(50, Some(persist::MAX_AGE));
+        let mut clipboard_provider = get_clipboard_provider();
+
+        let initial_clipboard = clipboard_provider
+            .get_clip_selection(ClipboardKind::Clipboard)
+            .unwrap_or(ClipContent::Text(String::new()));
+        let initial_primary = clipboard_provider
+            .get_clip_selection(ClipboardKind::Primary)
+            .unwrap_or(ClipContent::Text(String::new()));
+        // Passing `ctx` lets the monitor thread wake the (otherwise
+        // idle, reactive-mode) event loop itself as soon as it has
+        // something, rather than relying on some unrelated frame to
+        // come along and notice the channel has data.
+        let clip_events = monitor::spawn_monitor(ctx);
+
         Ok(Self {
             clipboard_stack,
-            clipboard_ctx,
-            last_clipboard_content: initial_content,
-            monitor_interval: Duration::from_millis(500),
+            clipboard_provider,
+            clip_events,
+            last_clipboard: initial_clipboard,
+            last_primary: initial_primary,
             auto_monitor: true,
             search_filter: String::new(),
+            kind_filter: None,
+            image_textures: HashMap::new(),
         })
     }
 
-    fn monitor_clipboard(&mut self) {
+    /// Drains any clips the background monitor thread has captured
+    /// since the last frame. Returns `true` if at least one was added,
+    /// so the caller knows to repaint.
+    fn drain_monitor_events(&mut self) -> bool {
         if !self.auto_monitor {
-            return;
+            // Leave events queued on the channel rather than discarding
+            // them: the monitor thread only sends on change, so a clip
+            // copied while paused would otherwise never be seen again
+            // once auto_monitor is re-enabled.
+            return false;
         }
-        
-        if let Ok(content) = self.clipboard_ctx.get_contents() {
-            if !content.is_empty() && content != self.last_clipboard_content {
-                let entry = ClipEntry::new(content.clone());
-                self.clipboard_stack.push(entry);
-                self.last_clipboard_content = content;
+
+        let mut received = false;
+        while let Ok((content, kind)) = self.clip_events.try_recv() {
+            let last = match kind {
+                ClipboardKind::Clipboard => &mut self.last_clipboard,
+                ClipboardKind::Primary => &mut self.last_primary,
+            };
+            if !content.is_empty() && content != *last {
+                self.clipboard_stack
+                    .push(ClipEntry::with_kind(content.clone(), kind));
+                *last = content;
+                received = true;
             }
         }
+        received
+    }
+
+    fn copy_to_clipboard(&mut self, content: ClipContent) {
+        self.copy_to_selection(content, ClipboardKind::Clipboard);
     }
 
-    fn copy_to_clipboard(&mut self, content: &str) {
-        if let Err(e) = self.clipboard_ctx.set_contents(content.to_string()) {
-            eprintln!("Failed to set clipboard: {}", e);
-        } else {
-            self.last_clipboard_content = content.to_string();
+    /// Republishes a clip to a specific X11 selection, e.g. re-sending a
+    /// history item to PRIMARY for a middle-click paste.
+    fn copy_to_selection(&mut self, content: ClipContent, kind: ClipboardKind) {
+        match self
+            .clipboard_provider
+            .set_clip_selection(kind, content.clone())
+        {
+            Ok(()) => match kind {
+                ClipboardKind::Clipboard => self.last_clipboard = content,
+                ClipboardKind::Primary => self.last_primary = content,
+            },
+            Err(e) => eprintln!("Failed to set clipboard: {}", e),
         }
     }
 
-    fn filtered_entries(&self) -> Vec<(usize, &ClipEntry)> {
-        if self.search_filter.is_empty() {
-            self.clipboard_stack.iter().enumerate().collect()
-        } else {
-            self.clipboard_stack
-                .iter()
-                .enumerate()
-                .filter(|(_, entry)| {
-                    entry.content.to_lowercase().contains(&self.search_filter.to_lowercase())
-                })
-                .collect()
+    /// Pushes a pinned register's clip back to the system clipboard.
+    fn copy_register(&mut self, label: char) {
+        if let Some(content) = self
+            .clipboard_stack
+            .register(label)
+            .map(|e| e.content.clone())
+        {
+            self.copy_to_clipboard(content);
         }
     }
+
+    fn content_preview(content: &ClipContent, max_len: usize) -> String {
+        let text = match content {
+            ClipContent::Text(text) => text.clone(),
+            ClipContent::Html { plain, .. } => plain.clone(),
+            ClipContent::Image { width, height, .. } => return format!("image {width}x{height}"),
+        };
+        truncate_preview(&text, max_len)
+    }
+
+    fn filtered_entries(&self) -> Vec<(usize, &ClipEntry)> {
+        let needle = self.search_filter.to_lowercase();
+        self.clipboard_stack
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| match self.kind_filter {
+                Some(kind) => entry.kind == kind,
+                None => true,
+            })
+            .filter(|(_, entry)| {
+                if needle.is_empty() {
+                    return true;
+                }
+                match &entry.content {
+                    ClipContent::Text(text) => text.to_lowercase().contains(&needle),
+                    ClipContent::Html { plain, .. } => plain.to_lowercase().contains(&needle),
+                    ClipContent::Image { .. } => false,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a cached `TextureHandle` for an image clip, uploading it
+    /// to the GPU the first time it's seen.
+    fn image_texture(
+        &mut self,
+        ctx: &egui::Context,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        hash: u64,
+    ) -> egui::TextureHandle {
+        self.image_textures
+            .entry(hash)
+            .or_insert_with(|| {
+                let image = egui::ColorImage::from_rgba_unmultiplied(
+                    [width as usize, height as usize],
+                    rgba,
+                );
+                ctx.load_texture(
+                    format!("clip-image-{hash}"),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                )
+            })
+            .clone()
+    }
 }
 
 impl eframe::App for ClipboardApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        
-        self.monitor_clipboard();
-        
-        
-        ctx.request_repaint_after(self.monitor_interval);
-        
+        if self.drain_monitor_events() {
+            ctx.request_repaint();
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("📋 Clipboard Manager");
             ui.separator();
-            
-            
+
+
             ui.horizontal(|ui| {
                 ui.checkbox(&mut self.auto_monitor, "Auto Monitor");
                 ui.separator();
-                
+
                 if ui.button("🔄 Refresh").clicked() {
-                    self.monitor_clipboard();
+                    self.drain_monitor_events();
                 }
-                
+
                 if ui.button("🗑️ Clear All").clicked() {
                     self.clipboard_stack.clear();
                 }
-                
+
                 ui.separator();
                 ui.label(format!("📊 {} items", self.clipboard_stack.len()));
             });
-            
+
             ui.separator();
-            
-            
+
+
             ui.horizontal(|ui| {
                 ui.label("🔍 Search:");
                 ui.text_edit_singleline(&mut self.search_filter);
                 if ui.button("✖").clicked() {
                     self.search_filter.clear();
                 }
+
+                ui.separator();
+                ui.label("Show:");
+                ui.radio_value(&mut self.kind_filter, None, "All");
+                ui.radio_value(
+                    &mut self.kind_filter,
+                    Some(ClipboardKind::Clipboard),
+                    "📋 Clipboard",
+                );
+                ui.radio_value(
+                    &mut self.kind_filter,
+                    Some(ClipboardKind::Primary),
+                    "🖱 Primary",
+                );
             });
-            
+
             ui.separator();
-            
-            
+
+
             ui.collapsing("ℹ️ Instructions", |ui| {
                 ui.label("• Copy text normally (Ctrl+C) - it will appear here automatically");
                 ui.label("• Click 'Copy' button to copy item back to clipboard");
                 ui.label("• Use search to filter through your clipboard history");
                 ui.label("• Toggle 'Auto Monitor' to pause/resume clipboard monitoring");
+                ui.label("• Image and HTML clips are captured too, shown with a thumbnail or badge");
+                ui.label("• Pin a clip to a letter (a-z) to keep it safe from eviction and Clear All");
+                ui.label("• History is saved to disk and restored on restart; toggle 🔓/🔒 to keep a clip memory-only");
+                ui.label("• On X11, the PRIMARY selection (text you highlight) is tracked too, marked 🖱 primary");
+                ui.label("• Use 'Show' to list only Clipboard or only Primary entries");
+                ui.label("• 'Copy→Primary' re-publishes a history item to PRIMARY instead of CLIPBOARD");
             });
-            
+
             ui.separator();
-            
-            
-            let filtered_entries = self.filtered_entries();
-            
-            
-            let mut entries_to_copy: Vec<String> = Vec::new();
-            
-            
+
+
+            let registers: Vec<(char, ClipContent)> = self
+                .clipboard_stack
+                .registers()
+                .into_iter()
+                .map(|(label, entry)| (label, entry.content.clone()))
+                .collect();
+            let mut registers_to_copy: Vec<char> = Vec::new();
+            let mut registers_to_unpin: Vec<char> = Vec::new();
+
+            if !registers.is_empty() {
+                ui.label("📌 Pinned registers");
+                ui.horizontal_wrapped(|ui| {
+                    for (label, content) in &registers {
+                        ui.group(|ui| {
+                            ui.label(format!("[{label}]"));
+                            if ui
+                                .button("📋")
+                                .on_hover_text(Self::content_preview(content, 80))
+                                .clicked()
+                            {
+                                registers_to_copy.push(*label);
+                            }
+                            if ui.small_button("✖").clicked() {
+                                registers_to_unpin.push(*label);
+                            }
+                        });
+                    }
+                });
+                ui.separator();
+            }
+
+            // Copy out everything the list needs up front: the entries
+            // borrow `self.clipboard_stack` immutably, but rendering an
+            // image clip needs `&mut self` to populate the texture
+            // cache, so the list can't stay borrowed while we draw it.
+            let rows: Vec<(usize, ClipContent, u64, std::time::SystemTime, bool, ClipboardKind)> = self
+                .filtered_entries()
+                .into_iter()
+                .map(|(i, entry)| {
+                    (
+                        i,
+                        entry.content.clone(),
+                        entry.content_hash,
+                        entry.timestamp,
+                        entry.no_persist,
+                        entry.kind,
+                    )
+                })
+                .collect();
+            let is_empty = rows.is_empty();
+
+            let mut entries_to_copy: Vec<ClipContent> = Vec::new();
+            let mut entries_to_copy_primary: Vec<ClipContent> = Vec::new();
+            let mut entries_to_pin: Vec<(char, ClipContent, std::time::SystemTime, bool, ClipboardKind)> =
+                Vec::new();
+            let mut entries_to_toggle_persist: Vec<usize> = Vec::new();
+
+
             egui::ScrollArea::vertical()
                 .auto_shrink([false; 2])
                 .show(ui, |ui| {
-                    if filtered_entries.is_empty() {
+                    if is_empty {
                         if self.search_filter.is_empty() {
                             ui.centered_and_justified(|ui| {
                                 ui.label("📝 No clipboard history yet.\nCopy something to get started!");
@@ -203,19 +330,60 @@ impl eframe::App for ClipboardApp {
                             });
                         }
                     } else {
-                        for (original_index, entry) in &filtered_entries {
+                        for (original_index, content, hash, timestamp, no_persist, kind) in rows {
                             ui.group(|ui| {
-                                
+
                                 ui.horizontal(|ui| {
                                     ui.label(format!("#{}", original_index + 1));
-                                    
+
+                                    match &content {
+                                        ClipContent::Image { .. } => {
+                                            ui.label("🖼 image");
+                                        }
+                                        ClipContent::Html { .. } => {
+                                            ui.label("🌐 html");
+                                        }
+                                        ClipContent::Text(_) => {}
+                                    }
+
+                                    if kind == ClipboardKind::Primary {
+                                        ui.label("🖱 primary");
+                                    }
+
+                                    let persist_icon = if no_persist { "🔒" } else { "🔓" };
+                                    if ui
+                                        .button(persist_icon)
+                                        .on_hover_text("Toggle whether this clip is saved to disk")
+                                        .clicked()
+                                    {
+                                        entries_to_toggle_persist.push(original_index);
+                                    }
+
+                                    ui.menu_button("📌 Pin to…", |ui| {
+                                        ui.horizontal_wrapped(|ui| {
+                                            for label in 'a'..='z' {
+                                                if ui.button(label.to_string()).clicked() {
+                                                    entries_to_pin.push((label, content.clone(), timestamp, no_persist, kind));
+                                                    ui.close_menu();
+                                                }
+                                            }
+                                        });
+                                    });
+
                                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                         if ui.button("📋 Copy").clicked() {
-                                            entries_to_copy.push(entry.content.clone());
+                                            entries_to_copy.push(content.clone());
+                                        }
+                                        if ui
+                                            .button("🖱 Copy→Primary")
+                                            .on_hover_text("Re-publish this clip to the PRIMARY selection")
+                                            .clicked()
+                                        {
+                                            entries_to_copy_primary.push(content.clone());
                                         }
-                                        
-                                        
-                                        if let Ok(duration) = entry.timestamp.elapsed() {
+
+
+                                        if let Ok(duration) = timestamp.elapsed() {
                                             let seconds = duration.as_secs();
                                             let time_str = if seconds < 60 {
                                                 format!("{}s ago", seconds)
@@ -228,58 +396,140 @@ impl eframe::App for ClipboardApp {
                                         }
                                     });
                                 });
-                                
+
                                 ui.separator();
-                                
-                                
-                                let mut preview = if entry.content.len() > 200 {
-                                    format!("{}...", &entry.content[..200])
-                                } else {
-                                    entry.content.clone()
-                                };
-                                
-                                
-                                ui.add(
-                                    egui::TextEdit::multiline(&mut preview)
-                                        .desired_rows(3)
-                                        .desired_width(f32::INFINITY)
-                                        .code_editor()
-                                );
-                                
-                                
-                                ui.horizontal(|ui| {
-                                    ui.label(format!("📏 {} chars", entry.content.len()));
-                                    ui.label(format!("📄 {} lines", entry.content.lines().count()));
-                                });
+
+                                match &content {
+                                    ClipContent::Text(text) => {
+                                        let mut preview = truncate_preview(text, 200);
+
+                                        ui.add(
+                                            egui::TextEdit::multiline(&mut preview)
+                                                .desired_rows(3)
+                                                .desired_width(f32::INFINITY)
+                                                .code_editor()
+                                        );
+
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("📏 {} chars", text.chars().count()));
+                                            ui.label(format!("📄 {} lines", text.lines().count()));
+                                        });
+                                    }
+                                    ClipContent::Html { plain, .. } => {
+                                        let mut preview = truncate_preview(plain, 200);
+
+                                        ui.add(
+                                            egui::TextEdit::multiline(&mut preview)
+                                                .desired_rows(3)
+                                                .desired_width(f32::INFINITY)
+                                                .code_editor()
+                                        );
+
+                                        ui.label(format!("📏 {} chars (plain-text preview)", plain.chars().count()));
+                                    }
+                                    ClipContent::Image { width, height, rgba } => {
+                                        let texture = self.image_texture(ctx, *width, *height, rgba, hash);
+                                        let max_width = 200.0_f32;
+                                        let scale = (max_width / *width as f32).min(1.0);
+                                        let size = egui::vec2(*width as f32 * scale, *height as f32 * scale);
+                                        ui.add(egui::Image::new(&texture).fit_to_exact_size(size));
+                                        ui.label(format!("🖼 {}x{}", width, height));
+                                    }
+                                }
                             });
-                            
+
                             ui.add_space(5.0);
                         }
                     }
                 });
-            
+
             for content in entries_to_copy {
-                self.copy_to_clipboard(&content);
+                self.copy_to_clipboard(content);
+            }
+            for content in entries_to_copy_primary {
+                self.copy_to_selection(content, ClipboardKind::Primary);
+            }
+            for (label, content, timestamp, no_persist, kind) in entries_to_pin {
+                self.clipboard_stack.pin(
+                    label,
+                    ClipEntry {
+                        content_hash: content.content_hash(),
+                        content,
+                        timestamp,
+                        no_persist,
+                        kind,
+                    },
+                );
+            }
+            for label in registers_to_copy {
+                self.copy_register(label);
+            }
+            for label in registers_to_unpin {
+                self.clipboard_stack.unpin(label);
+            }
+            for index in entries_to_toggle_persist {
+                if let Some(entry) = self.clipboard_stack.get_mut(index) {
+                    entry.no_persist = !entry.no_persist;
+                }
             }
         });
     }
+
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        persist::save(&self.clipboard_stack);
+    }
+}
+
+impl Drop for ClipboardApp {
+    fn drop(&mut self) {
+        persist::save(&self.clipboard_stack);
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let app = ClipboardApp::new()?;
-    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([600.0, 700.0])
             .with_min_inner_size([400.0, 300.0]),
         ..Default::default()
     };
-    
+
     eframe::run_native(
         "Clipboard Manager",
         options,
-        Box::new(|_cc| Box::new(app)),
+        Box::new(|cc| {
+            Box::new(
+                ClipboardApp::new(cc.egui_ctx.clone())
+                    .expect("failed to initialize clipboard manager"),
+            )
+        }),
     )?;
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_preview_leaves_short_strings_untouched() {
+        assert_eq!(truncate_preview("hello", 200), "hello");
+    }
+
+    #[test]
+    fn truncate_preview_cuts_on_a_char_count_not_byte_count() {
+        // "é" and "ö" are 2 bytes each, so 5 bytes would stop at "héll".
+        let text = "héllo wörld";
+        assert_eq!(truncate_preview(text, 5), "héllo...");
+    }
+
+    #[test]
+    fn truncate_preview_does_not_panic_on_a_multibyte_boundary() {
+        // Every character here is 3 bytes in UTF-8, so a byte-index
+        // slice at an arbitrary offset would land mid-character.
+        let text = "€".repeat(100);
+        let preview = truncate_preview(&text, 80);
+        assert_eq!(preview.chars().count(), 83); // 80 chars + "..."
+    }
+}
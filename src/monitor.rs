@@ -0,0 +1,272 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::clip::{ClipContent, ClipboardKind};
+use crate::provider::get_clipboard_provider;
+
+/// Spawns a background thread that watches the system clipboard and
+/// forwards new contents over the returned channel as soon as they
+/// change, instead of the UI thread polling on a fixed interval. Each
+/// captured clip is tagged with which selection it came from; outside
+/// X11/Wayland there's no PRIMARY selection, so everything is tagged
+/// `Clipboard`.
+///
+/// `ctx` is cloned into the monitor thread so it can call
+/// `request_repaint()` itself right after sending: eframe only runs
+/// `update` (where the UI thread normally drains the channel and
+/// requests a repaint) in reactive mode when something wakes it, so
+/// without this a clip copied while the window is idle would sit
+/// unread until an unrelated frame happened to come along.
+///
+/// Uses native change notifications where available (Windows'
+/// `AddClipboardFormatListener`/`WM_CLIPBOARDUPDATE`, X11's XFixes
+/// selection-owner-change event) and falls back to a short interval
+/// poll everywhere else, or if the native hook fails to set up.
+pub fn spawn_monitor(ctx: egui::Context) -> Receiver<(ClipContent, ClipboardKind)> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        #[cfg(target_os = "windows")]
+        {
+            windows_backend::watch(tx, ctx);
+            return;
+        }
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            if x11_backend::watch(&tx, &ctx) {
+                return;
+            }
+        }
+
+        poll_backend::watch(tx, ctx);
+    });
+
+    rx
+}
+
+/// Interval-poll fallback used on platforms without a native
+/// clipboard-change notification, or when setting one up fails.
+mod poll_backend {
+    use super::*;
+
+    pub fn watch(tx: Sender<(ClipContent, ClipboardKind)>, ctx: egui::Context) {
+        let mut provider = get_clipboard_provider();
+        let mut last_clipboard = provider
+            .get_clip_selection(ClipboardKind::Clipboard)
+            .unwrap_or(ClipContent::Text(String::new()));
+        let mut last_primary = provider
+            .get_clip_selection(ClipboardKind::Primary)
+            .unwrap_or(ClipContent::Text(String::new()));
+
+        loop {
+            thread::sleep(Duration::from_millis(500));
+
+            if let Ok(content) = provider.get_clip_selection(ClipboardKind::Clipboard) {
+                if !content.is_empty() && content != last_clipboard {
+                    last_clipboard = content.clone();
+                    if tx.send((content, ClipboardKind::Clipboard)).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+
+            // Errors here just mean the platform/backend has no
+            // PRIMARY selection (e.g. macOS, Windows) - nothing to log.
+            if let Ok(content) = provider.get_clip_selection(ClipboardKind::Primary) {
+                if !content.is_empty() && content != last_primary {
+                    last_primary = content.clone();
+                    if tx.send((content, ClipboardKind::Primary)).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11_backend {
+    use super::*;
+    use x11rb::connect;
+    use x11rb::protocol::xfixes::{self, ConnectionExt as _};
+    use x11rb::protocol::Event;
+
+    /// Subscribes to XFixes selection-owner-change notifications for
+    /// both `CLIPBOARD` and `PRIMARY`, tagging each forwarded clip with
+    /// the selection it came from. Returns `true` if the watch loop ran
+    /// (and has now ended, e.g. the receiver was dropped), or `false`
+    /// if XFixes isn't available so the caller should fall back to
+    /// polling.
+    pub fn watch(tx: &Sender<(ClipContent, ClipboardKind)>, ctx: &egui::Context) -> bool {
+        let (conn, screen_num) = match connect(None) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+        let version_query = match conn.xfixes_query_version(5, 0) {
+            Ok(cookie) => cookie,
+            Err(_) => return false,
+        };
+        if version_query.reply().is_err() {
+            return false;
+        }
+
+        let window = conn.setup().roots[screen_num].root;
+        let clipboard_atom = match conn
+            .intern_atom(false, b"CLIPBOARD")
+            .and_then(|c| c.reply())
+        {
+            Ok(r) => r.atom,
+            Err(_) => return false,
+        };
+        // PRIMARY is a predefined X11 atom (1), no intern_atom needed.
+        let primary_atom = x11rb::protocol::xproto::AtomEnum::PRIMARY.into();
+
+        let mask = xfixes::SelectionEventMask::SET_SELECTION_OWNER
+            | xfixes::SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | xfixes::SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        if conn
+            .xfixes_select_selection_input(window, clipboard_atom, mask)
+            .is_err()
+            || conn
+                .xfixes_select_selection_input(window, primary_atom, mask)
+                .is_err()
+        {
+            return false;
+        }
+
+        let mut provider = get_clipboard_provider();
+        loop {
+            let event = match conn.wait_for_event() {
+                Ok(e) => e,
+                Err(_) => return true,
+            };
+            if let Event::XfixesSelectionNotify(notify) = event {
+                let kind = if notify.selection == primary_atom {
+                    ClipboardKind::Primary
+                } else {
+                    ClipboardKind::Clipboard
+                };
+                if let Ok(content) = provider.get_clip_selection(kind) {
+                    if !content.is_empty() {
+                        if tx.send((content, kind)).is_err() {
+                            return true;
+                        }
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_backend {
+    use super::*;
+
+    /// Creates a hidden message-only window, registers it with
+    /// `AddClipboardFormatListener`, and pumps `WM_CLIPBOARDUPDATE`
+    /// messages, forwarding clipboard text as it changes. Windows has
+    /// no PRIMARY selection, so everything is tagged `Clipboard`. Falls
+    /// back to polling if the listener can't be registered.
+    pub fn watch(tx: Sender<(ClipContent, ClipboardKind)>, ctx: egui::Context) {
+        if !message_window::run(&tx, &ctx) {
+            poll_backend::watch(tx, ctx);
+        }
+    }
+
+    mod message_window {
+        use super::*;
+        use std::ptr;
+        use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+        use winapi::shared::windef::HWND;
+        use winapi::um::winuser::{
+            AddClipboardFormatListener, CreateWindowExW, DefWindowProcW, DispatchMessageW,
+            GetMessageW, RegisterClassW, TranslateMessage, HWND_MESSAGE, MSG, WM_CLIPBOARDUPDATE,
+            WNDCLASSW,
+        };
+
+        thread_local! {
+            static SENDER: std::cell::RefCell<Option<Sender<(ClipContent, ClipboardKind)>>> =
+                std::cell::RefCell::new(None);
+            static CTX: std::cell::RefCell<Option<egui::Context>> = std::cell::RefCell::new(None);
+        }
+
+        unsafe extern "system" fn wnd_proc(
+            hwnd: HWND,
+            msg: u32,
+            wparam: WPARAM,
+            lparam: LPARAM,
+        ) -> LRESULT {
+            if msg == WM_CLIPBOARDUPDATE {
+                SENDER.with(|sender| {
+                    if let Some(tx) = sender.borrow().as_ref() {
+                        let mut provider = get_clipboard_provider();
+                        if let Ok(content) = provider.get_clip() {
+                            if tx.send((content, ClipboardKind::Clipboard)).is_ok() {
+                                CTX.with(|ctx| {
+                                    if let Some(ctx) = ctx.borrow().as_ref() {
+                                        ctx.request_repaint();
+                                    }
+                                });
+                            }
+                        }
+                    }
+                });
+            }
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+
+        /// Returns `false` if the listener window couldn't be created.
+        pub fn run(tx: &Sender<(ClipContent, ClipboardKind)>, ctx: &egui::Context) -> bool {
+            SENDER.with(|sender| *sender.borrow_mut() = Some(tx.clone()));
+            CTX.with(|cell| *cell.borrow_mut() = Some(ctx.clone()));
+
+            unsafe {
+                let class_name: Vec<u16> = "ClipboardManagerListener\0".encode_utf16().collect();
+                let wc = WNDCLASSW {
+                    lpfnWndProc: Some(wnd_proc),
+                    lpszClassName: class_name.as_ptr(),
+                    ..std::mem::zeroed()
+                };
+                if RegisterClassW(&wc) == 0 {
+                    return false;
+                }
+
+                let hwnd = CreateWindowExW(
+                    0,
+                    class_name.as_ptr(),
+                    ptr::null(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    HWND_MESSAGE,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                );
+                if hwnd.is_null() {
+                    return false;
+                }
+
+                if AddClipboardFormatListener(hwnd) == 0 {
+                    return false;
+                }
+
+                let mut msg: MSG = std::mem::zeroed();
+                while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+                    TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+            true
+        }
+    }
+}
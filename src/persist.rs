@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clip::{ClipEntry, ClipboardStack};
+
+const APP_DIR: &str = "clipboard-manager";
+const STORE_FILE: &str = "history.json";
+
+/// How long persisted history is kept before `load` prunes it, on top
+/// of the existing `max_size` rolling eviction.
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// On-disk shape of the store: the rolling history plus the pinned
+/// registers, so a register (sold as surviving eviction and Clear All)
+/// also survives a restart.
+#[derive(Serialize, Deserialize)]
+struct PersistedHistory {
+    entries: Vec<ClipEntry>,
+    #[serde(default)]
+    registers: Vec<(char, ClipEntry)>,
+}
+
+fn store_path() -> Option<PathBuf> {
+    let mut dir = dirs::config_dir()?;
+    dir.push(APP_DIR);
+    Some(dir.join(STORE_FILE))
+}
+
+/// Loads persisted history (if any) into a fresh `ClipboardStack`,
+/// dropping entries older than `max_age` as they're restored.
+pub fn load(max_size: usize, max_age: Option<Duration>) -> ClipboardStack {
+    let mut stack = ClipboardStack::new(max_size);
+
+    let Some(path) = store_path() else {
+        return stack;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return stack;
+    };
+    let Some(persisted) = parse_persisted(&data) else {
+        return stack;
+    };
+
+    let cutoff = max_age.map(|age| {
+        SystemTime::now()
+            .checked_sub(age)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    });
+    restore_into(&mut stack, persisted, cutoff);
+    stack
+}
+
+/// Parses the store's JSON, falling back to the older bare-
+/// `Vec<ClipEntry>` format (from before registers were persisted) so an
+/// existing history file doesn't just vanish the first time this runs.
+fn parse_persisted(data: &str) -> Option<PersistedHistory> {
+    serde_json::from_str::<PersistedHistory>(data)
+        .ok()
+        .or_else(|| {
+            serde_json::from_str::<Vec<ClipEntry>>(data)
+                .ok()
+                .map(|entries| PersistedHistory {
+                    entries,
+                    registers: Vec::new(),
+                })
+        })
+}
+
+/// Restores `persisted` into `stack`, dropping entries (but never
+/// registers) older than `cutoff`.
+fn restore_into(stack: &mut ClipboardStack, persisted: PersistedHistory, cutoff: Option<SystemTime>) {
+    for mut entry in persisted.entries {
+        if let Some(cutoff) = cutoff {
+            if entry.timestamp < cutoff {
+                continue;
+            }
+        }
+        // `content_hash` isn't persisted (it's a pure function of
+        // `content`), so it comes back as the default and needs
+        // recomputing once here, on load rather than every frame.
+        entry.content_hash = entry.content.content_hash();
+        stack.push_back_raw(entry);
+    }
+    for (label, mut entry) in persisted.registers {
+        entry.content_hash = entry.content.content_hash();
+        stack.pin(label, entry);
+    }
+}
+
+/// Builds the on-disk shape of `stack`, skipping entries and registers
+/// flagged `no_persist` so sensitive clips never hit disk.
+fn persistable(stack: &ClipboardStack) -> PersistedHistory {
+    let entries: Vec<ClipEntry> = stack.iter().filter(|e| !e.no_persist).cloned().collect();
+    let registers: Vec<(char, ClipEntry)> = stack
+        .registers()
+        .into_iter()
+        .filter(|(_, e)| !e.no_persist)
+        .map(|(label, entry)| (label, entry.clone()))
+        .collect();
+    PersistedHistory { entries, registers }
+}
+
+/// Writes the current history to disk as JSON, skipping entries
+/// flagged `no_persist` so sensitive clips never hit disk.
+pub fn save(stack: &ClipboardStack) {
+    let Some(path) = store_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return;
+        }
+    }
+
+    let persisted = persistable(stack);
+    match serde_json::to_string_pretty(&persisted) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("Failed to save clipboard history: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize clipboard history: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clip::ClipContent;
+
+    fn entry_at(content: &str, timestamp: SystemTime) -> ClipEntry {
+        let mut entry = ClipEntry::new(ClipContent::Text(content.to_string()));
+        entry.timestamp = timestamp;
+        entry
+    }
+
+    #[test]
+    fn restore_into_drops_entries_older_than_cutoff() {
+        let now = SystemTime::now();
+        let cutoff = now - Duration::from_secs(60);
+        let persisted = PersistedHistory {
+            entries: vec![
+                entry_at("old", now - Duration::from_secs(120)),
+                entry_at("recent", now),
+            ],
+            registers: Vec::new(),
+        };
+
+        let mut stack = ClipboardStack::new(10);
+        restore_into(&mut stack, persisted, Some(cutoff));
+
+        assert_eq!(stack.len(), 1);
+        assert_eq!(
+            stack.get(0).unwrap().content,
+            ClipContent::Text("recent".to_string())
+        );
+    }
+
+    #[test]
+    fn restore_into_keeps_registers_regardless_of_age() {
+        let now = SystemTime::now();
+        let cutoff = now - Duration::from_secs(60);
+        let persisted = PersistedHistory {
+            entries: Vec::new(),
+            registers: vec![('a', entry_at("ancient pin", now - Duration::from_secs(999_999)))],
+        };
+
+        let mut stack = ClipboardStack::new(10);
+        restore_into(&mut stack, persisted, Some(cutoff));
+
+        assert_eq!(
+            stack.register('a').unwrap().content,
+            ClipContent::Text("ancient pin".to_string())
+        );
+    }
+
+    #[test]
+    fn persistable_skips_no_persist_entries_and_registers() {
+        let mut stack = ClipboardStack::new(10);
+
+        let mut kept = ClipEntry::new(ClipContent::Text("kept".to_string()));
+        kept.no_persist = false;
+        stack.push(kept);
+
+        let mut secret = ClipEntry::new(ClipContent::Text("secret".to_string()));
+        secret.no_persist = true;
+        stack.push(secret);
+
+        let mut pinned_secret = ClipEntry::new(ClipContent::Text("pinned secret".to_string()));
+        pinned_secret.no_persist = true;
+        stack.pin('a', pinned_secret);
+
+        let persisted = persistable(&stack);
+
+        assert_eq!(persisted.entries.len(), 1);
+        assert_eq!(persisted.entries[0].content, ClipContent::Text("kept".to_string()));
+        assert!(persisted.registers.is_empty());
+    }
+
+    #[test]
+    fn parse_persisted_accepts_the_older_bare_entries_format() {
+        let old_format = r#"[{"content":{"Text":"legacy"},"timestamp":0,"no_persist":false}]"#;
+        let persisted = parse_persisted(old_format).expect("should parse legacy format");
+
+        assert_eq!(persisted.entries.len(), 1);
+        assert_eq!(
+            persisted.entries[0].content,
+            ClipContent::Text("legacy".to_string())
+        );
+        assert!(persisted.registers.is_empty());
+    }
+}
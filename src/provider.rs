@@ -0,0 +1,415 @@
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::clip::{ClipContent, ClipboardKind};
+
+/// Abstraction over a system clipboard backend.
+///
+/// `ClipboardApp` talks to whichever implementation `get_clipboard_provider`
+/// picks at startup instead of depending on the `clipboard` crate directly,
+/// so headless/Wayland-only setups where that crate can't find a backend
+/// still get a working (in-memory) history.
+pub trait ClipboardProvider {
+    /// Human-readable name of the backend, shown in diagnostics/UI.
+    fn name(&self) -> &'static str;
+
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>>;
+
+    fn set_contents(&mut self, content: String) -> Result<(), Box<dyn Error>>;
+
+    /// Reads whichever clipboard format is present, preferring an image,
+    /// then HTML, falling back to plain text. The default implementation
+    /// only knows about text; backends that can see richer formats
+    /// override it.
+    fn get_clip(&mut self) -> Result<ClipContent, Box<dyn Error>> {
+        self.get_contents().map(ClipContent::Text)
+    }
+
+    /// Publishes a captured clip back to the system clipboard. The
+    /// default implementation only knows how to write text: an HTML
+    /// clip is published as its plain-text rendering, and an image clip
+    /// is an error.
+    fn set_clip(&mut self, content: ClipContent) -> Result<(), Box<dyn Error>> {
+        match content {
+            ClipContent::Text(text) => self.set_contents(text),
+            ClipContent::Html { plain, .. } => self.set_contents(plain),
+            ClipContent::Image { .. } => Err("this backend can't publish image clips".into()),
+        }
+    }
+
+    /// Same as `get_clip`, but for a specific X11-style selection. The
+    /// default implementation only knows about `Clipboard`; backends
+    /// with no notion of a separate PRIMARY selection (Windows, macOS)
+    /// simply error out for `Primary`, which callers treat as "not
+    /// available on this platform" rather than a hard failure.
+    fn get_clip_selection(&mut self, kind: ClipboardKind) -> Result<ClipContent, Box<dyn Error>> {
+        match kind {
+            ClipboardKind::Clipboard => self.get_clip(),
+            ClipboardKind::Primary => Err("this backend has no PRIMARY selection".into()),
+        }
+    }
+
+    /// Same as `set_clip`, but targeting a specific selection.
+    fn set_clip_selection(
+        &mut self,
+        kind: ClipboardKind,
+        content: ClipContent,
+    ) -> Result<(), Box<dyn Error>> {
+        match kind {
+            ClipboardKind::Clipboard => self.set_clip(content),
+            ClipboardKind::Primary => Err("this backend has no PRIMARY selection".into()),
+        }
+    }
+}
+
+/// Which family of clipboard command-line tools a `CommandProvider`
+/// wraps. Each family exposes richer formats (image, HTML) through its
+/// own flags, so the provider branches on this instead of juggling
+/// separate callback fields per format.
+enum CommandKind {
+    WlClipboard,
+    Xclip,
+    PbCopyPaste,
+}
+
+/// Runs external commands to talk to the system clipboard, e.g.
+/// `wl-copy`/`wl-paste`, `xclip`, or `pbpaste`/`pbcopy`.
+struct CommandProvider {
+    kind: CommandKind,
+}
+
+impl CommandProvider {
+    fn run(prog: &str, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let output = Command::new(prog).args(args).output()?;
+        if !output.status.success() {
+            return Err(format!("{} exited with {}", prog, output.status).into());
+        }
+        Ok(output.stdout)
+    }
+
+    fn run_with_stdin(prog: &str, args: &[&str], input: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut child = Command::new(prog)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or("failed to open stdin")?
+            .write_all(input)?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", prog, status).into());
+        }
+        Ok(())
+    }
+
+    /// Text read/write commands are identical across formats except for
+    /// which selection they target, so this is the one place that knows
+    /// how each backend spells "clipboard" vs. "primary".
+    fn get_text(&self, kind: ClipboardKind) -> Result<String, Box<dyn Error>> {
+        let bytes = match (&self.kind, kind) {
+            (CommandKind::WlClipboard, ClipboardKind::Clipboard) => {
+                Self::run("wl-paste", &["--no-newline"])?
+            }
+            (CommandKind::WlClipboard, ClipboardKind::Primary) => {
+                Self::run("wl-paste", &["--primary", "--no-newline"])?
+            }
+            (CommandKind::Xclip, ClipboardKind::Clipboard) => {
+                Self::run("xclip", &["-selection", "clipboard", "-o"])?
+            }
+            (CommandKind::Xclip, ClipboardKind::Primary) => {
+                Self::run("xclip", &["-selection", "primary", "-o"])?
+            }
+            (CommandKind::PbCopyPaste, ClipboardKind::Clipboard) => Self::run("pbpaste", &[])?,
+            (CommandKind::PbCopyPaste, ClipboardKind::Primary) => {
+                return Err("pbcopy/pbpaste has no PRIMARY selection".into())
+            }
+        };
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn set_text(&self, kind: ClipboardKind, content: &str) -> Result<(), Box<dyn Error>> {
+        match (&self.kind, kind) {
+            (CommandKind::WlClipboard, ClipboardKind::Clipboard) => {
+                Self::run_with_stdin("wl-copy", &[], content.as_bytes())
+            }
+            (CommandKind::WlClipboard, ClipboardKind::Primary) => {
+                Self::run_with_stdin("wl-copy", &["--primary"], content.as_bytes())
+            }
+            (CommandKind::Xclip, ClipboardKind::Clipboard) => {
+                Self::run_with_stdin("xclip", &["-selection", "clipboard"], content.as_bytes())
+            }
+            (CommandKind::Xclip, ClipboardKind::Primary) => {
+                Self::run_with_stdin("xclip", &["-selection", "primary"], content.as_bytes())
+            }
+            (CommandKind::PbCopyPaste, ClipboardKind::Clipboard) => {
+                Self::run_with_stdin("pbcopy", &[], content.as_bytes())
+            }
+            (CommandKind::PbCopyPaste, ClipboardKind::Primary) => {
+                Err("pbcopy/pbpaste has no PRIMARY selection".into())
+            }
+        }
+    }
+
+    /// Writes pre-encoded PNG bytes to whichever `image/png` target the
+    /// backend exposes, the write-side counterpart to `get_image`.
+    fn set_image(&self, kind: ClipboardKind, png_bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        match (&self.kind, kind) {
+            (CommandKind::WlClipboard, ClipboardKind::Clipboard) => {
+                Self::run_with_stdin("wl-copy", &["--type", "image/png"], png_bytes)
+            }
+            (CommandKind::WlClipboard, ClipboardKind::Primary) => {
+                Self::run_with_stdin("wl-copy", &["--primary", "--type", "image/png"], png_bytes)
+            }
+            (CommandKind::Xclip, ClipboardKind::Clipboard) => Self::run_with_stdin(
+                "xclip",
+                &["-selection", "clipboard", "-t", "image/png"],
+                png_bytes,
+            ),
+            (CommandKind::Xclip, ClipboardKind::Primary) => Self::run_with_stdin(
+                "xclip",
+                &["-selection", "primary", "-t", "image/png"],
+                png_bytes,
+            ),
+            (CommandKind::PbCopyPaste, _) => {
+                Err(format!("{} can't publish image clips back yet", self.name()).into())
+            }
+        }
+    }
+
+    /// Tries to read a PNG from whichever `image/png` target the
+    /// backend exposes, decoding it into raw RGBA. Returns `None` (not
+    /// an error) when no image is on the clipboard, so the caller can
+    /// fall through to HTML/text.
+    fn get_image(&self, kind: ClipboardKind) -> Option<ClipContent> {
+        let png_bytes = match (&self.kind, kind) {
+            (CommandKind::WlClipboard, ClipboardKind::Clipboard) => {
+                Self::run("wl-paste", &["--type", "image/png"]).ok()?
+            }
+            (CommandKind::WlClipboard, ClipboardKind::Primary) => {
+                Self::run("wl-paste", &["--primary", "--type", "image/png"]).ok()?
+            }
+            (CommandKind::Xclip, ClipboardKind::Clipboard) => Self::run(
+                "xclip",
+                &["-selection", "clipboard", "-t", "image/png", "-o"],
+            )
+            .ok()?,
+            (CommandKind::Xclip, ClipboardKind::Primary) => {
+                Self::run("xclip", &["-selection", "primary", "-t", "image/png", "-o"]).ok()?
+            }
+            (CommandKind::PbCopyPaste, _) => return None,
+        };
+        if png_bytes.is_empty() {
+            return None;
+        }
+        let img = image::load_from_memory(&png_bytes).ok()?;
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        Some(ClipContent::Image {
+            width,
+            height,
+            rgba: rgba.into_raw(),
+        })
+    }
+
+    fn get_html(&self, kind: ClipboardKind) -> Option<ClipContent> {
+        let html_bytes = match (&self.kind, kind) {
+            (CommandKind::WlClipboard, ClipboardKind::Clipboard) => {
+                Self::run("wl-paste", &["--type", "text/html"]).ok()?
+            }
+            (CommandKind::WlClipboard, ClipboardKind::Primary) => {
+                Self::run("wl-paste", &["--primary", "--type", "text/html"]).ok()?
+            }
+            (CommandKind::Xclip, ClipboardKind::Clipboard) => Self::run(
+                "xclip",
+                &["-selection", "clipboard", "-t", "text/html", "-o"],
+            )
+            .ok()?,
+            (CommandKind::Xclip, ClipboardKind::Primary) => {
+                Self::run("xclip", &["-selection", "primary", "-t", "text/html", "-o"]).ok()?
+            }
+            (CommandKind::PbCopyPaste, _) => return None,
+        };
+        if html_bytes.is_empty() {
+            return None;
+        }
+        let html = String::from_utf8_lossy(&html_bytes).into_owned();
+        let plain = self.get_text(kind).unwrap_or_default();
+        Some(ClipContent::Html { html, plain })
+    }
+
+    fn get_clip_for(&self, kind: ClipboardKind) -> Result<ClipContent, Box<dyn Error>> {
+        if let Some(image) = self.get_image(kind) {
+            return Ok(image);
+        }
+        if let Some(html) = self.get_html(kind) {
+            return Ok(html);
+        }
+        self.get_text(kind).map(ClipContent::Text)
+    }
+
+    /// Images are re-encoded as PNG and published under `image/png`.
+    /// HTML clips are published as their plain-text rendering only; the
+    /// `text/html` markup is not written back.
+    fn set_clip_for(
+        &self,
+        kind: ClipboardKind,
+        content: ClipContent,
+    ) -> Result<(), Box<dyn Error>> {
+        match content {
+            ClipContent::Text(text) => self.set_text(kind, &text),
+            ClipContent::Html { plain, .. } => self.set_text(kind, &plain),
+            ClipContent::Image {
+                width,
+                height,
+                rgba,
+            } => {
+                let buffer: image::RgbaImage = image::ImageBuffer::from_raw(width, height, rgba)
+                    .ok_or("image buffer doesn't match its declared dimensions")?;
+                let mut png_bytes = Vec::new();
+                image::DynamicImage::ImageRgba8(buffer)
+                    .write_to(&mut std::io::Cursor::new(&mut png_bytes), image::ImageFormat::Png)
+                    .map_err(|e| format!("failed to encode image as PNG: {e}"))?;
+                self.set_image(kind, &png_bytes)
+            }
+        }
+    }
+}
+
+impl ClipboardProvider for CommandProvider {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            CommandKind::WlClipboard => "wl-clipboard",
+            CommandKind::Xclip => "xclip",
+            CommandKind::PbCopyPaste => "pbcopy/pbpaste",
+        }
+    }
+
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>> {
+        self.get_text(ClipboardKind::Clipboard)
+    }
+
+    fn set_contents(&mut self, content: String) -> Result<(), Box<dyn Error>> {
+        self.set_text(ClipboardKind::Clipboard, &content)
+    }
+
+    fn get_clip(&mut self) -> Result<ClipContent, Box<dyn Error>> {
+        self.get_clip_for(ClipboardKind::Clipboard)
+    }
+
+    fn set_clip(&mut self, content: ClipContent) -> Result<(), Box<dyn Error>> {
+        self.set_clip_for(ClipboardKind::Clipboard, content)
+    }
+
+    fn get_clip_selection(&mut self, kind: ClipboardKind) -> Result<ClipContent, Box<dyn Error>> {
+        self.get_clip_for(kind)
+    }
+
+    fn set_clip_selection(
+        &mut self,
+        kind: ClipboardKind,
+        content: ClipContent,
+    ) -> Result<(), Box<dyn Error>> {
+        self.set_clip_for(kind, content)
+    }
+}
+
+/// In-memory fallback used when no system clipboard command is reachable
+/// (e.g. a headless box with neither `wl-copy`, `xclip`, nor `pbcopy` on
+/// `PATH`). History capture and replay keep working; it just never leaves
+/// the process.
+struct NopProvider {
+    buffer: ClipContent,
+    primary: ClipContent,
+}
+
+impl NopProvider {
+    fn new() -> Self {
+        Self {
+            buffer: ClipContent::Text(String::new()),
+            primary: ClipContent::Text(String::new()),
+        }
+    }
+}
+
+impl ClipboardProvider for NopProvider {
+    fn name(&self) -> &'static str {
+        "in-memory (no system clipboard found)"
+    }
+
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>> {
+        match &self.buffer {
+            ClipContent::Text(text) => Ok(text.clone()),
+            ClipContent::Html { plain, .. } => Ok(plain.clone()),
+            ClipContent::Image { .. } => Ok(String::new()),
+        }
+    }
+
+    fn set_contents(&mut self, content: String) -> Result<(), Box<dyn Error>> {
+        self.buffer = ClipContent::Text(content);
+        Ok(())
+    }
+
+    fn get_clip(&mut self) -> Result<ClipContent, Box<dyn Error>> {
+        Ok(self.buffer.clone())
+    }
+
+    fn set_clip(&mut self, content: ClipContent) -> Result<(), Box<dyn Error>> {
+        self.buffer = content;
+        Ok(())
+    }
+
+    fn get_clip_selection(&mut self, kind: ClipboardKind) -> Result<ClipContent, Box<dyn Error>> {
+        match kind {
+            ClipboardKind::Clipboard => Ok(self.buffer.clone()),
+            ClipboardKind::Primary => Ok(self.primary.clone()),
+        }
+    }
+
+    fn set_clip_selection(
+        &mut self,
+        kind: ClipboardKind,
+        content: ClipContent,
+    ) -> Result<(), Box<dyn Error>> {
+        match kind {
+            ClipboardKind::Clipboard => self.buffer = content,
+            ClipboardKind::Primary => self.primary = content,
+        }
+        Ok(())
+    }
+}
+
+fn command_exists(cmd: &str) -> bool {
+    let path_var = match std::env::var_os("PATH") {
+        Some(p) => p,
+        None => return false,
+    };
+    std::env::split_paths(&path_var).any(|dir| dir.join(cmd).is_file())
+}
+
+/// Picks the best available clipboard backend for the current machine,
+/// in order: Wayland (`wl-copy`/`wl-paste`), X11 (`xclip`), macOS
+/// (`pbcopy`/`pbpaste`), falling back to an in-memory buffer.
+pub fn get_clipboard_provider() -> Box<dyn ClipboardProvider> {
+    if command_exists("wl-copy") && command_exists("wl-paste") {
+        return Box::new(CommandProvider {
+            kind: CommandKind::WlClipboard,
+        });
+    }
+
+    if command_exists("xclip") {
+        return Box::new(CommandProvider {
+            kind: CommandKind::Xclip,
+        });
+    }
+
+    if command_exists("pbcopy") && command_exists("pbpaste") {
+        return Box::new(CommandProvider {
+            kind: CommandKind::PbCopyPaste,
+        });
+    }
+
+    Box::new(NopProvider::new())
+}